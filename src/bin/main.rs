@@ -1,25 +1,94 @@
-use clap::Parser;
-use csv_search::{parse_query, LoadedCSV};
+use clap::{Parser, Subcommand};
+use csv_search::{parse_query, LoadedCSV, LoadedDataset, Query, QueryOutput};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
 struct Args {
-    /// Input file to process.
-    #[arg(long)]
-    input: std::path::PathBuf,
-    /// Query to run.
-    #[arg(long)]
-    query: String,
+    /// Input file to load, as `name=path`. Repeat to load several files and join them in a
+    /// `FROM` clause (e.g. `--input a=left.csv --input b=right.csv`); with a single `--input`,
+    /// a query with no `FROM` clause at all runs against that file without naming it.
+    #[arg(long = "input", required = true, value_parser = parse_input)]
+    inputs: Vec<(String, std::path::PathBuf)>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a single query and print its output.
+    Query {
+        /// Query to run.
+        #[arg(long)]
+        query: String,
+        /// Bind a `:name` parameter used in the query, as `name=value`. May be repeated.
+        #[arg(long = "param", value_parser = parse_binding)]
+        params: Vec<(String, String)>,
+    },
+    /// Load the input files once, then run many queries interactively.
+    Repl,
+}
+
+/// Parse a `--param` flag's `name=value` argument.
+fn parse_binding(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid --param '{s}', expected name=value"))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Parse an `--input` flag's `name=path` argument.
+fn parse_input(s: &str) -> Result<(String, std::path::PathBuf), String> {
+    let (name, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid --input '{s}', expected name=path"))?;
+    Ok((name.to_string(), std::path::PathBuf::from(path)))
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let mut dataset = LoadedDataset::new();
+    for (name, path) in args.inputs {
+        dataset.insert(name, LoadedCSV::from_path(path)?);
+    }
 
-    let data = LoadedCSV::from_path(args.input)?;
-    let query = parse_query(&args.query)?;
-    let query_output = data.execute_query(query)?;
+    match args.command {
+        Command::Query { query, params } => {
+            let query = parse_query(&query)?;
+            let bindings: HashMap<String, String> = params.into_iter().collect();
+            let query_output = execute_query(&dataset, query, &bindings)?;
+            write_csv(query_output)?;
+        }
+        Command::Repl => run_repl(&dataset)?,
+    }
+    Ok(())
+}
 
-    // Output filtered rows.
+/// Dispatch `query` to [`LoadedCSV::execute_query_with`] when exactly one file was loaded and the
+/// query either has no `FROM` clause or names that one file, or to
+/// [`LoadedDataset::execute_query_with`] to join across several loaded files. A query that names a
+/// table absent from the loaded set always falls through to `LoadedDataset::execute_query_with`,
+/// so its "Unknown table" validation applies even when only one file was loaded.
+fn execute_query<'a>(
+    dataset: &'a LoadedDataset,
+    query: Query,
+    bindings: &HashMap<String, String>,
+) -> anyhow::Result<QueryOutput<'a>> {
+    let sole_csv = match (dataset.tables.len(), query.tables()) {
+        (1, []) => dataset.tables.values().next(),
+        (1, [name]) => dataset.tables.get(name),
+        _ => None,
+    };
+    match sole_csv {
+        Some(csv) => csv.execute_query_with(query, bindings),
+        None => dataset.execute_query_with(query, bindings),
+    }
+}
+
+/// Write a query's output as CSV to stdout.
+fn write_csv(query_output: QueryOutput<'_>) -> anyhow::Result<()> {
     let out_writer = std::io::stdout().lock();
     let mut writer = csv::WriterBuilder::new().from_writer(out_writer);
     writer.write_record(&query_output.headers)?;
@@ -29,3 +98,152 @@ fn main() -> anyhow::Result<()> {
     writer.flush()?;
     Ok(())
 }
+
+/// Accumulate input lines until the user ends the statement with `;`, then parse and execute it
+/// against `dataset` and print the result as an aligned table. Submission only happens on `;` so
+/// that a multi-line `FILTER` clause isn't mistaken for a finished query just because a shorter
+/// prefix of the buffered input happens to parse on its own. Meta-commands `:schema`, `:count`,
+/// `:history` and `:quit` are recognized between statements.
+fn run_repl(dataset: &LoadedDataset) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let mut history: Vec<String> = Vec::new();
+    let mut buffer = String::new();
+
+    print!("> ");
+    stdout.flush()?;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let trimmed = line.trim();
+
+        if buffer.is_empty() {
+            match trimmed {
+                ":quit" | ":q" => break,
+                ":schema" => {
+                    let mut names: Vec<&String> = dataset.tables.keys().collect();
+                    names.sort();
+                    for name in names {
+                        let csv = &dataset.tables[name];
+                        for (col, ty) in csv.column_names.iter().zip(csv.rows.types.iter()) {
+                            println!("{name}.{col}: {ty:?}");
+                        }
+                    }
+                    print!("> ");
+                    stdout.flush()?;
+                    continue;
+                }
+                ":count" => {
+                    let mut names: Vec<&String> = dataset.tables.keys().collect();
+                    names.sort();
+                    for name in names {
+                        println!("{name}: {}", dataset.tables[name].rows.len());
+                    }
+                    print!("> ");
+                    stdout.flush()?;
+                    continue;
+                }
+                ":history" => {
+                    for (i, past) in history.iter().enumerate() {
+                        println!("{}: {past}", i + 1);
+                    }
+                    print!("> ");
+                    stdout.flush()?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if !buffer.is_empty() {
+            buffer.push(' ');
+        }
+        buffer.push_str(trimmed);
+        let terminated = buffer.ends_with(';');
+
+        if terminated {
+            let candidate = buffer.trim_end_matches(';').to_string();
+            buffer.clear();
+            match parse_query(&candidate) {
+                Ok(query) => {
+                    history.push(candidate);
+                    match execute_query(dataset, query, &HashMap::new()) {
+                        Ok(output) => print_table(output),
+                        Err(e) => eprintln!("Error: {e:#}"),
+                    }
+                }
+                Err(e) => eprintln!("Error: {e:#}"),
+            }
+        }
+
+        print!("{}", if buffer.is_empty() { "> " } else { "... " });
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Print a query's output as a table with columns aligned to the widest cell (or header).
+fn print_table(output: QueryOutput<'_>) {
+    let headers = output.headers.clone();
+    let rows: Vec<Vec<String>> = output.collect();
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    print_row(&headers, &widths);
+    for row in &rows {
+        print_row(row, &widths);
+    }
+}
+
+fn print_row(cells: &[String], widths: &[usize]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, w)| format!("{cell:<w$}"))
+        .collect();
+    println!("{}", line.join(" | "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_csv() -> LoadedDataset {
+        let mut dataset = LoadedDataset::new();
+        dataset.insert(
+            "foo",
+            LoadedCSV::from_reader("id,name\n1,alice\n".as_bytes()).unwrap(),
+        );
+        dataset
+    }
+
+    #[test]
+    fn test_execute_query_single_input_no_from_clause() -> anyhow::Result<()> {
+        let dataset = one_csv();
+        let query = parse_query("PROJECT name")?;
+        let rows: Vec<Vec<String>> = execute_query(&dataset, query, &HashMap::new())?.collect();
+        assert_eq!(rows, vec![vec!["alice".to_string()]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_query_single_input_matching_from_clause() -> anyhow::Result<()> {
+        let dataset = one_csv();
+        let query = parse_query("PROJECT name FROM foo")?;
+        let rows: Vec<Vec<String>> = execute_query(&dataset, query, &HashMap::new())?.collect();
+        assert_eq!(rows, vec![vec!["alice".to_string()]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_query_single_input_rejects_unknown_from_alias() {
+        let dataset = one_csv();
+        let query = parse_query("PROJECT name FROM bar").unwrap();
+        let err = execute_query(&dataset, query, &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("Unknown table 'bar'"));
+    }
+}
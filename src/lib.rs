@@ -2,19 +2,57 @@
 //! The main entrypoints to use are
 //!
 //! - [`LoadedCSV`] and its methods to load CSV into memory
+//! - [`LoadedDataset`] to load several CSVs and query across them, e.g. to join.
 //! - [`Query`]. Use `parse_query` to parse a query from a string.
-//!   Then use `execute_query` method of `LoadedCSV` to get the iterator over the output records.
+//!   Then use `execute_query` method of `LoadedCSV` (or `LoadedDataset`) to get the iterator
+//!   over the output records.
 
 mod parser;
 
 use anyhow::Context as _;
 use csv::StringRecord;
 pub use parser::{parse_query, Query};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColumnType {
     String,
     Integer, // For simplicity this means i64.
+    Float,   // f64.
+    Boolean,
+    Date, // Stored and compared as an epoch-day integer, see `parse_date`.
+}
+
+/// Parse a `yyyy-mm-dd` date into the number of days since the Unix epoch (1970-01-01), using the
+/// proleptic Gregorian calendar. This is Howard Hinnant's `days_from_civil` algorithm, chosen so
+/// dates can be compared as plain integers without pulling in a date/time dependency.
+fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400; // [0, 399]
+    let mp = (month + 9) % 12; // [0, 11]
+    let doy = (153 * mp + 2) / 5 + day - 1; // [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // [0, 146096]
+    Some(era * 146097 + doe - 719468)
+}
+
+/// The number of days in `month` (1-12) of `year`, accounting for leap years. `month` is assumed
+/// already validated to be in range.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        _ => unreachable!("month is validated to be in 1..=12"),
+    }
 }
 
 #[derive(Debug)]
@@ -24,11 +62,26 @@ pub struct Rows {
 }
 
 impl Rows {
+    /// Fold one more row into the loaded rows, relaxing each column's inferred type just enough
+    /// to still accommodate it. Types only ever widen along `Boolean -> Integer -> Float ->
+    /// String`, with `Date` sitting beside `Integer` (tried after it, which is safe since a date
+    /// string is never itself a valid integer) and falling straight back to `String` on a mismatch.
     pub fn push(&mut self, row: csv::StringRecord) {
         for (i, ty) in self.types.iter_mut().enumerate() {
             let col = row.get(i).unwrap(); // TODO
             *ty = match *ty {
-                ColumnType::Integer if col.parse::<i64>().is_ok() => ColumnType::Integer,
+                ColumnType::Boolean if col.parse::<bool>().is_ok() => ColumnType::Boolean,
+                ColumnType::Boolean | ColumnType::Integer if col.parse::<i64>().is_ok() => {
+                    ColumnType::Integer
+                }
+                ColumnType::Boolean | ColumnType::Date if parse_date(col).is_some() => {
+                    ColumnType::Date
+                }
+                ColumnType::Boolean | ColumnType::Integer | ColumnType::Float
+                    if col.parse::<f64>().is_ok() =>
+                {
+                    ColumnType::Float
+                }
                 _ => ColumnType::String,
             };
         }
@@ -38,9 +91,19 @@ impl Rows {
     pub fn empty(num_columns: usize) -> Self {
         Self {
             rows: Vec::new(),
-            types: vec![ColumnType::Integer; num_columns],
+            types: vec![ColumnType::Boolean; num_columns],
         }
     }
+
+    /// The number of rows loaded.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether no rows have been loaded.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
 }
 
 /// A loaded CSV file.
@@ -83,44 +146,461 @@ impl LoadedCSV {
     /// Attempt to validate the query on the data, and then execute it.
     /// Validation can fail, and in that case an error will be returned.
     ///
-    /// Otherwise an iterator over the output
+    /// Otherwise an iterator over the output.
+    ///
+    /// The query must not have a `FROM` clause naming more than one table; to join several
+    /// loaded files use [`LoadedDataset::execute_query`] instead.
     pub fn execute_query(&self, query: Query) -> anyhow::Result<QueryOutput<'_>> {
-        let compiled_query = query.compile(&self.rows.types, &self.column_names)?;
+        self.execute_query_with(query, &HashMap::new())
+    }
+
+    /// Like [`execute_query`](Self::execute_query), but resolves any `:param` placeholders in
+    /// the query's filters against `bindings` rather than requiring every value to be an inline
+    /// string constant. A query with no placeholders can be run with an empty map.
+    pub fn execute_query_with(
+        &self,
+        query: Query,
+        bindings: &HashMap<String, String>,
+    ) -> anyhow::Result<QueryOutput<'_>> {
+        anyhow::ensure!(
+            query.tables().len() <= 1,
+            "Use LoadedDataset::execute_query to run a query across more than one table."
+        );
+        // Owned so the borrow of `query` ends here, before `query.compile` below takes `query`
+        // by value.
+        let table_name = query.tables().first().cloned().unwrap_or_default();
+        let schema = (
+            table_name.as_str(),
+            self.rows.types.as_slice(),
+            self.column_names.as_slice(),
+        );
+        let compiled_query = query.compile(&[schema], bindings)?;
         let headers = compiled_query.out_header();
-        Ok(QueryOutput {
-            compiled_query,
-            headers,
-            iter: self.rows.rows.iter(),
-        })
+        let source = RowSource::Single(self.rows.rows.iter());
+        Ok(QueryOutput::new(compiled_query, headers, source))
+    }
+}
+
+/// Several loaded CSV files, keyed by the alias a query's `FROM` clause uses to refer to them.
+#[derive(Default)]
+pub struct LoadedDataset {
+    pub tables: HashMap<String, LoadedCSV>,
+}
+
+impl LoadedDataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) a loaded CSV file under the given alias.
+    pub fn insert(&mut self, name: impl Into<String>, csv: LoadedCSV) {
+        self.tables.insert(name.into(), csv);
+    }
+
+    /// Validate the query against the named tables' schemas, and then execute it.
+    ///
+    /// The query must have a `FROM` clause naming one or two of the dataset's tables; two
+    /// tables are joined with a hash join on the single equality filter between them.
+    pub fn execute_query(&self, query: Query) -> anyhow::Result<QueryOutput<'_>> {
+        self.execute_query_with(query, &HashMap::new())
+    }
+
+    /// Like [`execute_query`](Self::execute_query), but resolves any `:param` placeholders in
+    /// the query's filters against `bindings` rather than requiring every value to be an inline
+    /// string constant. A query with no placeholders can be run with an empty map.
+    pub fn execute_query_with(
+        &self,
+        query: Query,
+        bindings: &HashMap<String, String>,
+    ) -> anyhow::Result<QueryOutput<'_>> {
+        anyhow::ensure!(
+            !query.tables().is_empty(),
+            "Query must have a FROM clause naming at least one table."
+        );
+        // Owned so the borrow of `query` ends here, before `query.compile` below takes `query`
+        // by value.
+        let table_names: Vec<String> = query.tables().to_vec();
+        let mut schemas = Vec::with_capacity(table_names.len());
+        let mut csvs = Vec::with_capacity(table_names.len());
+        for name in &table_names {
+            let csv = self
+                .tables
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown table '{name}'"))?;
+            schemas.push((name.as_str(), csv.rows.types.as_slice(), csv.column_names.as_slice()));
+            csvs.push(csv);
+        }
+        let compiled_query = query.compile(&schemas, bindings)?;
+        let headers = compiled_query.out_header();
+
+        let source = match csvs[..] {
+            [csv] => RowSource::Single(csv.rows.rows.iter()),
+            [csv0, csv1] => {
+                let join = compiled_query
+                    .join
+                    .clone()
+                    .expect("compile() guarantees a join spec for a two-table query");
+                let build_is_table0 = csv0.rows.rows.len() <= csv1.rows.rows.len();
+                let (build_csv, build_column, probe_csv) = if build_is_table0 {
+                    (csv0, join.table0_column, csv1)
+                } else {
+                    (csv1, join.table1_column, csv0)
+                };
+                let mut build_index: HashMap<JoinKey, Vec<usize>> = HashMap::new();
+                for (idx, row) in build_csv.rows.rows.iter().enumerate() {
+                    let Some(key) = JoinKey::from_field(join.ty, row.get(build_column).unwrap())
+                    else {
+                        // The join column doesn't parse as the expected type on this row;
+                        // drop it rather than panicking later.
+                        continue;
+                    };
+                    build_index.entry(key).or_default().push(idx);
+                }
+                RowSource::Join {
+                    build_rows: build_csv.rows.rows.as_slice(),
+                    probe_rows: probe_csv.rows.rows.iter(),
+                    build_is_table0,
+                    build_index,
+                    join,
+                    current_probe: None,
+                    current_matches: Vec::new().into_iter(),
+                }
+            }
+            _ => unreachable!("compile() rejects more than two tables"),
+        };
+        Ok(QueryOutput::new(compiled_query, headers, source))
+    }
+}
+
+/// The value of a join column, typed so that e.g. `"007"` and `"7"` correctly collide as equal
+/// when the join column is an integer column instead of spuriously missing each other the way a
+/// raw-text comparison would, and so a `Float`/`Date` column joins on its parsed value rather than
+/// its raw text (e.g. `"3.0"` and `"3.00"`, or two differently-formatted but equal dates, must
+/// match).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum JoinKey {
+    Integer(i64),
+    /// The bit pattern of a parsed float, with `-0.0` canonicalized to `0.0` to match `==`.
+    /// `NaN` fields never produce a key (see `from_field`), consistent with `NaN != NaN`.
+    Float(u64),
+    String(String),
+}
+
+impl JoinKey {
+    fn from_field(ty: ColumnType, field: &str) -> Option<JoinKey> {
+        match ty {
+            ColumnType::Integer => field.parse::<i64>().ok().map(JoinKey::Integer),
+            ColumnType::Date => parse_date(field).map(JoinKey::Integer),
+            ColumnType::Float => field.parse::<f64>().ok().and_then(|v| {
+                if v.is_nan() {
+                    None
+                } else {
+                    let v = if v == 0.0 { 0.0 } else { v };
+                    Some(JoinKey::Float(v.to_bits()))
+                }
+            }),
+            ColumnType::String | ColumnType::Boolean => Some(JoinKey::String(field.to_owned())),
+        }
+    }
+}
+
+/// Supplies the composite rows (one `StringRecord` per table participating in the query, in
+/// `FROM` order) a query's filters are checked against, before those filters are applied.
+enum RowSource<'a> {
+    Single(std::slice::Iter<'a, StringRecord>),
+    /// A hash join between two tables: `build_rows`/`build_index` hold the smaller side,
+    /// indexed by join key, while `probe_rows` streams the larger side.
+    Join {
+        build_rows: &'a [StringRecord],
+        probe_rows: std::slice::Iter<'a, StringRecord>,
+        build_is_table0: bool,
+        build_index: HashMap<JoinKey, Vec<usize>>,
+        join: JoinSpec,
+        current_probe: Option<&'a StringRecord>,
+        current_matches: std::vec::IntoIter<usize>,
+    },
+}
+
+impl<'a> RowSource<'a> {
+    fn next_candidate(&mut self) -> Option<Vec<&'a StringRecord>> {
+        match self {
+            RowSource::Single(iter) => iter.next().map(|record| vec![record]),
+            RowSource::Join {
+                build_rows,
+                probe_rows,
+                build_is_table0,
+                build_index,
+                join,
+                current_probe,
+                current_matches,
+            } => loop {
+                if let Some(build_idx) = current_matches.next() {
+                    let build_row = &build_rows[build_idx];
+                    let probe_row = current_probe.expect("set alongside current_matches");
+                    return Some(if *build_is_table0 {
+                        vec![build_row, probe_row]
+                    } else {
+                        vec![probe_row, build_row]
+                    });
+                }
+                let probe_row = probe_rows.next()?;
+                *current_probe = Some(probe_row);
+                let probe_column = if *build_is_table0 {
+                    join.table1_column
+                } else {
+                    join.table0_column
+                };
+                let key = JoinKey::from_field(join.ty, probe_row.get(probe_column).unwrap());
+                *current_matches = match key {
+                    Some(key) => build_index.get(&key).cloned().unwrap_or_default().into_iter(),
+                    // Join column doesn't parse as the expected type; no matches.
+                    None => Vec::new().into_iter(),
+                };
+            },
+        }
+    }
+}
+
+/// The next composite row from `source` that passes `filters` (every row passes when there is no
+/// `FILTER` clause at all).
+fn next_filtered_row<'a>(
+    filters: &Option<CompiledFilterTree>,
+    source: &mut RowSource<'a>,
+) -> Option<Vec<&'a StringRecord>> {
+    loop {
+        let candidate = source.next_candidate()?;
+        let passes = match filters {
+            Some(f) => f.eval(&candidate),
+            None => true,
+        };
+        if passes {
+            return Some(candidate);
+        }
     }
 }
 
 pub struct QueryOutput<'a> {
-    compiled_query: CompiledQuery,
     pub headers: Vec<String>,
-    iter: std::slice::Iter<'a, StringRecord>,
+    exec: QueryExec<'a>,
+}
+
+enum QueryExec<'a> {
+    Project {
+        compiled: CompiledQuery,
+        source: RowSource<'a>,
+    },
+    Aggregate {
+        compiled: CompiledQuery,
+        source: RowSource<'a>,
+        /// `None` until the first call to `next`, which drains `source` completely to compute
+        /// every group before any row can be yielded.
+        results: Option<std::vec::IntoIter<Vec<String>>>,
+    },
+}
+
+impl<'a> QueryOutput<'a> {
+    fn new(compiled: CompiledQuery, headers: Vec<String>, source: RowSource<'a>) -> QueryOutput<'a> {
+        let exec = match &compiled.shape {
+            QueryShape::Project { .. } => QueryExec::Project { compiled, source },
+            QueryShape::Aggregate { .. } => QueryExec::Aggregate {
+                compiled,
+                source,
+                results: None,
+            },
+        };
+        QueryOutput { headers, exec }
+    }
 }
 
 impl<'a> Iterator for QueryOutput<'a> {
-    type Item = Vec<&'a str>;
+    type Item = Vec<String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for record in self.iter.by_ref() {
-            let out = self.compiled_query.on_row(record);
-            if out.is_some() {
-                return out;
+        match &mut self.exec {
+            QueryExec::Project { compiled, source } => {
+                let QueryShape::Project { projections } = &compiled.shape else {
+                    unreachable!("a Project exec always has a Project shape")
+                };
+                let row = next_filtered_row(&compiled.filters, source)?;
+                Some(
+                    projections
+                        .iter()
+                        .map(|((table_idx, column_idx), _)| {
+                            row[*table_idx].get(*column_idx).unwrap().to_owned()
+                        })
+                        .collect(),
+                )
+            }
+            QueryExec::Aggregate {
+                compiled,
+                source,
+                results,
+            } => {
+                let results = results.get_or_insert_with(|| run_aggregation(compiled, source).into_iter());
+                results.next()
+            }
+        }
+    }
+}
+
+/// Drain `source` fully, partitioning its filtered rows into groups and finalizing each group's
+/// accumulators, producing one output row per group.
+fn run_aggregation(compiled: &CompiledQuery, source: &mut RowSource<'_>) -> Vec<Vec<String>> {
+    let QueryShape::Aggregate {
+        group_by,
+        aggregates,
+        output,
+        ..
+    } = &compiled.shape
+    else {
+        unreachable!("run_aggregation is only called for an Aggregate shape")
+    };
+
+    let mut groups: HashMap<Vec<String>, Vec<Accumulator>> = HashMap::new();
+    while let Some(row) = next_filtered_row(&compiled.filters, source) {
+        let key: Vec<String> = group_by
+            .iter()
+            .map(|(table_idx, column_idx)| row[*table_idx].get(*column_idx).unwrap().to_owned())
+            .collect();
+        let accs = groups
+            .entry(key)
+            .or_insert_with(|| aggregates.iter().map(|(kind, ty, _)| Accumulator::new(*kind, *ty)).collect());
+        for (acc, (_, _, (table_idx, column_idx))) in accs.iter_mut().zip(aggregates.iter()) {
+            acc.update(row[*table_idx].get(*column_idx).unwrap());
+        }
+    }
+    if groups.is_empty() && group_by.is_empty() {
+        // No GROUP BY: the whole (possibly empty) table is one group, e.g. COUNT should read 0
+        // rather than producing no output at all.
+        groups.insert(
+            Vec::new(),
+            aggregates.iter().map(|(kind, ty, _)| Accumulator::new(*kind, *ty)).collect(),
+        );
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, accs)| {
+            let finals: Vec<String> = accs.into_iter().map(Accumulator::finalize).collect();
+            output
+                .iter()
+                .map(|col| match col {
+                    OutputColumn::GroupBy(idx) => key[*idx].clone(),
+                    OutputColumn::Aggregate(idx) => finals[*idx].clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AggKind {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggKind {
+    /// The keyword this aggregate is written with in a query, also used to label its column in
+    /// the output header.
+    fn label(self) -> &'static str {
+        match self {
+            AggKind::Count => "COUNT",
+            AggKind::Sum => "SUM",
+            AggKind::Avg => "AVG",
+            AggKind::Min => "MIN",
+            AggKind::Max => "MAX",
+        }
+    }
+}
+
+/// The running state of one aggregate projection for one group.
+#[derive(Debug)]
+enum Accumulator {
+    Count(u64),
+    SumInt(i64),
+    AvgInt { sum: i64, count: u64 },
+    MinInt(Option<i64>),
+    MaxInt(Option<i64>),
+    MinStr(Option<String>),
+    MaxStr(Option<String>),
+}
+
+impl Accumulator {
+    fn new(kind: AggKind, ty: ColumnType) -> Accumulator {
+        match (kind, ty) {
+            (AggKind::Count, _) => Accumulator::Count(0),
+            (AggKind::Sum, ColumnType::Integer) => Accumulator::SumInt(0),
+            (AggKind::Avg, ColumnType::Integer) => Accumulator::AvgInt { sum: 0, count: 0 },
+            (AggKind::Min, ColumnType::Integer) => Accumulator::MinInt(None),
+            (AggKind::Max, ColumnType::Integer) => Accumulator::MaxInt(None),
+            (AggKind::Min, ColumnType::String) => Accumulator::MinStr(None),
+            (AggKind::Max, ColumnType::String) => Accumulator::MaxStr(None),
+            _ => unreachable!(
+                "compile() only allows SUM/AVG/MIN/MAX on integer columns, plus MIN/MAX on string columns"
+            ),
+        }
+    }
+
+    /// Fold one more field's value into this accumulator. The field is assumed to parse
+    /// according to the column type this accumulator was built for, as guaranteed by the
+    /// consistent typing of a loaded column (see [`Rows::push`]).
+    fn update(&mut self, field: &str) {
+        match self {
+            Accumulator::Count(n) => *n += 1,
+            Accumulator::SumInt(sum) => *sum += field.parse::<i64>().unwrap(),
+            Accumulator::AvgInt { sum, count } => {
+                *sum += field.parse::<i64>().unwrap();
+                *count += 1;
+            }
+            Accumulator::MinInt(cur) => {
+                let val = field.parse::<i64>().unwrap();
+                *cur = Some(cur.map_or(val, |c| c.min(val)));
+            }
+            Accumulator::MaxInt(cur) => {
+                let val = field.parse::<i64>().unwrap();
+                *cur = Some(cur.map_or(val, |c| c.max(val)));
+            }
+            Accumulator::MinStr(cur) => match cur {
+                Some(c) if c.as_str() <= field => {}
+                _ => *cur = Some(field.to_owned()),
+            },
+            Accumulator::MaxStr(cur) => match cur {
+                Some(c) if c.as_str() >= field => {}
+                _ => *cur = Some(field.to_owned()),
+            },
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Accumulator::Count(n) => n.to_string(),
+            Accumulator::SumInt(sum) => sum.to_string(),
+            Accumulator::AvgInt { sum, count } => {
+                if count == 0 {
+                    "0".to_string()
+                } else {
+                    (sum / count as i64).to_string()
+                }
+            }
+            Accumulator::MinInt(val) | Accumulator::MaxInt(val) => {
+                val.map(|v| v.to_string()).unwrap_or_default()
             }
-            // else try the next one, or terminate.
+            Accumulator::MinStr(val) | Accumulator::MaxStr(val) => val.unwrap_or_default(),
         }
-        None
     }
 }
 
 #[derive(Debug)]
 enum CompiledExpr {
-    Var { column_idx: usize },
+    Var { table_idx: usize, column_idx: usize },
     IntConst { val: i64 },
     StringConst { val: String },
+    FloatConst { val: f64 },
+    BoolConst { val: bool },
+    DateConst { val: i64 },
 }
 
 impl CompiledExpr {
@@ -129,30 +609,90 @@ impl CompiledExpr {
     ///
     /// This precondition is meant to be ensured by validation/compilation of the schema.
     /// If the precondition is violated this method will panic.
-    fn get_int(&self, ctx: &StringRecord) -> i64 {
+    fn get_int(&self, ctx: &[&StringRecord]) -> i64 {
         match self {
-            CompiledExpr::Var { column_idx } => {
-                ctx.get(*column_idx).unwrap().parse::<i64>().unwrap()
-            }
+            CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            } => ctx[*table_idx]
+                .get(*column_idx)
+                .unwrap()
+                .parse::<i64>()
+                .unwrap(),
             CompiledExpr::IntConst { val } => *val,
-            CompiledExpr::StringConst { .. } => {
-                panic!("Precondition violation. Got string constant but asking for an int.")
-            }
+            _ => panic!("Precondition violation. Expression is not an int."),
         }
     }
 
-    /// Get an integer out of the expression. This function assumes
-    /// that either the expression is an int constant, or that can be parsed.
+    /// Get a string out of the expression. This function assumes
+    /// that either the expression is a string constant, or that can be parsed.
     ///
     /// This precondition is meant to be ensured by validation/compilation of the schema.
     /// If the precondition is violated this method will panic.
-    fn get_str<'a>(&'a self, ctx: &'a StringRecord) -> &'a str {
+    fn get_str<'a>(&'a self, ctx: &[&'a StringRecord]) -> &'a str {
         match self {
-            CompiledExpr::Var { column_idx } => ctx.get(*column_idx).unwrap(),
-            CompiledExpr::IntConst { .. } => {
-                panic!("Precondition violation. Got int constant but asking for a string.")
-            }
+            CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            } => ctx[*table_idx].get(*column_idx).unwrap(),
             CompiledExpr::StringConst { val } => val,
+            _ => panic!("Precondition violation. Expression is not a string."),
+        }
+    }
+
+    /// Get a float out of the expression. This function assumes
+    /// that either the expression is a float constant, or that can be parsed.
+    ///
+    /// This precondition is meant to be ensured by validation/compilation of the schema.
+    /// If the precondition is violated this method will panic.
+    fn get_float(&self, ctx: &[&StringRecord]) -> f64 {
+        match self {
+            CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            } => ctx[*table_idx]
+                .get(*column_idx)
+                .unwrap()
+                .parse::<f64>()
+                .unwrap(),
+            CompiledExpr::FloatConst { val } => *val,
+            _ => panic!("Precondition violation. Expression is not a float."),
+        }
+    }
+
+    /// Get a boolean out of the expression. This function assumes
+    /// that either the expression is a bool constant, or that can be parsed.
+    ///
+    /// This precondition is meant to be ensured by validation/compilation of the schema.
+    /// If the precondition is violated this method will panic.
+    fn get_bool(&self, ctx: &[&StringRecord]) -> bool {
+        match self {
+            CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            } => ctx[*table_idx]
+                .get(*column_idx)
+                .unwrap()
+                .parse::<bool>()
+                .unwrap(),
+            CompiledExpr::BoolConst { val } => *val,
+            _ => panic!("Precondition violation. Expression is not a bool."),
+        }
+    }
+
+    /// Get a date (as an epoch-day integer, see [`parse_date`]) out of the expression. This
+    /// function assumes that either the expression is a date constant, or that can be parsed.
+    ///
+    /// This precondition is meant to be ensured by validation/compilation of the schema.
+    /// If the precondition is violated this method will panic.
+    fn get_date(&self, ctx: &[&StringRecord]) -> i64 {
+        match self {
+            CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            } => parse_date(ctx[*table_idx].get(*column_idx).unwrap()).unwrap(),
+            CompiledExpr::DateConst { val } => *val,
+            _ => panic!("Precondition violation. Expression is not a date."),
         }
     }
 }
@@ -160,16 +700,22 @@ impl CompiledExpr {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Test {
     Equal,
+    NotEqual,
     Greater,
     GreaterOrEqual,
+    Less,
+    LessOrEqual,
 }
 
 impl Test {
     pub fn test<A: PartialOrd>(self, left: A, right: A) -> bool {
         match self {
             Test::Equal => left == right,
+            Test::NotEqual => left != right,
             Test::Greater => left > right,
             Test::GreaterOrEqual => left >= right,
+            Test::Less => left < right,
+            Test::LessOrEqual => left <= right,
         }
     }
 }
@@ -182,15 +728,8 @@ struct CompiledFilter {
     test: Test,
 }
 
-/// A query processed in the context of a schema, and ready to execute.
-#[derive(Debug)]
-struct CompiledQuery {
-    projections: Vec<(usize, String)>,
-    filters: Vec<CompiledFilter>,
-}
-
 impl CompiledFilter {
-    fn check_record(&self, row: &StringRecord) -> bool {
+    fn check_record(&self, row: &[&StringRecord]) -> bool {
         match self.ty {
             ColumnType::String => {
                 let l = self.left.get_str(row);
@@ -202,30 +741,308 @@ impl CompiledFilter {
                 let r = self.right.get_int(row);
                 self.test.test(l, r)
             }
+            ColumnType::Float => {
+                let l = self.left.get_float(row);
+                let r = self.right.get_float(row);
+                self.test.test(l, r)
+            }
+            ColumnType::Boolean => {
+                let l = self.left.get_bool(row);
+                let r = self.right.get_bool(row);
+                self.test.test(l, r)
+            }
+            ColumnType::Date => {
+                let l = self.left.get_date(row);
+                let r = self.right.get_date(row);
+                self.test.test(l, r)
+            }
         }
     }
-}
 
-impl CompiledQuery {
-    /// Evaluate the compiled query on the given row and output
-    /// a row if the filter matches.
-    ///
-    /// This assumes that the record belongs to the data on which the query was compiled,
-    /// otherwise the behaviour is not well-defined and this method might panic.
-    fn on_row<'a>(&self, record: &'a StringRecord) -> Option<Vec<&'a str>> {
-        if self.filters.iter().all(|filter| filter.check_record(record)) {
-            Some(
-                self.projections
-                    .iter()
-                    .map(|i| &record[i.0])
-                    .collect::<Vec<_>>(),
+    /// Whether this filter is an equi-join predicate between the two tables of a two-table
+    /// query, i.e. `a.col = b.col` (in either order).
+    fn is_equi_join_predicate(&self) -> bool {
+        self.test == Test::Equal
+            && matches!(
+                (&self.left, &self.right),
+                (
+                    CompiledExpr::Var { table_idx: 0, .. },
+                    CompiledExpr::Var { table_idx: 1, .. }
+                ) | (
+                    CompiledExpr::Var { table_idx: 1, .. },
+                    CompiledExpr::Var { table_idx: 0, .. }
+                )
             )
+    }
+}
+
+/// A boolean combination of filters, recursively evaluated short-circuit. Produced by compiling
+/// a [`parser::FilterTree`] against a schema.
+#[derive(Debug)]
+enum CompiledFilterTree {
+    Leaf(CompiledFilter),
+    And(Box<CompiledFilterTree>, Box<CompiledFilterTree>),
+    Or(Box<CompiledFilterTree>, Box<CompiledFilterTree>),
+    Not(Box<CompiledFilterTree>),
+}
+
+impl CompiledFilterTree {
+    fn eval(&self, row: &[&StringRecord]) -> bool {
+        match self {
+            CompiledFilterTree::Leaf(f) => f.check_record(row),
+            CompiledFilterTree::And(l, r) => l.eval(row) && r.eval(row),
+            CompiledFilterTree::Or(l, r) => l.eval(row) || r.eval(row),
+            CompiledFilterTree::Not(t) => !t.eval(row),
+        }
+    }
+}
+
+/// The single equi-join predicate driving a two-table query's hash join, normalized so that
+/// `table0_column` always refers to the first table named in the `FROM` clause.
+#[derive(Debug, Clone)]
+struct JoinSpec {
+    table0_column: usize,
+    table1_column: usize,
+    ty: ColumnType,
+}
+
+impl JoinSpec {
+    fn from_predicate(predicate: CompiledFilter) -> JoinSpec {
+        let CompiledFilter { left, right, ty, .. } = predicate;
+        let (
+            CompiledExpr::Var {
+                table_idx: left_table,
+                column_idx: left_column,
+            },
+            CompiledExpr::Var {
+                table_idx: right_table,
+                column_idx: right_column,
+            },
+        ) = (left, right)
+        else {
+            unreachable!("is_equi_join_predicate guarantees both sides are columns");
+        };
+        debug_assert!((left_table, right_table) == (0, 1) || (left_table, right_table) == (1, 0));
+        if left_table == 0 {
+            JoinSpec {
+                table0_column: left_column,
+                table1_column: right_column,
+                ty,
+            }
         } else {
-            None
+            JoinSpec {
+                table0_column: right_column,
+                table1_column: left_column,
+                ty,
+            }
+        }
+    }
+}
+
+/// Flatten the top-level conjunction of `tree` into its conjuncts, stopping at `Or`/`Not`
+/// boundaries (those are kept whole, since only a bare `Leaf` can be a join predicate).
+fn flatten_and(tree: CompiledFilterTree, out: &mut Vec<CompiledFilterTree>) {
+    match tree {
+        CompiledFilterTree::And(l, r) => {
+            flatten_and(*l, out);
+            flatten_and(*r, out);
         }
+        other => out.push(other),
+    }
+}
+
+/// Re-fold the conjuncts left over after pulling out a join predicate into a single tree, or
+/// `None` if none are left (an always-true filter).
+fn fold_and(mut conjuncts: Vec<CompiledFilterTree>) -> Option<CompiledFilterTree> {
+    let mut tree = conjuncts.pop()?;
+    while let Some(next) = conjuncts.pop() {
+        tree = CompiledFilterTree::And(Box::new(next), Box::new(tree));
+    }
+    Some(tree)
+}
+
+/// Pull the join predicate (when there are two tables) out of `filters`, leaving the rest as
+/// residual filters applied to the joined row. The join predicate must appear as a top-level
+/// (AND-ed) conjunct; one nested inside `OR`/`NOT` would not hold for every joined row.
+fn extract_join(
+    filters: Option<CompiledFilterTree>,
+    num_tables: usize,
+) -> anyhow::Result<(Option<CompiledFilterTree>, Option<JoinSpec>)> {
+    if num_tables != 2 {
+        return Ok((filters, None));
+    }
+    let mut conjuncts = Vec::new();
+    if let Some(tree) = filters {
+        flatten_and(tree, &mut conjuncts);
+    }
+    let pos = conjuncts
+        .iter()
+        .position(|c| matches!(c, CompiledFilterTree::Leaf(f) if f.is_equi_join_predicate()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "A query over two tables requires a top-level equality filter joining them, e.g. 'a.id = b.user_id'."
+            )
+        })?;
+    let CompiledFilterTree::Leaf(join_filter) = conjuncts.remove(pos) else {
+        unreachable!("position() located a Leaf");
+    };
+    let join = JoinSpec::from_predicate(join_filter);
+    Ok((fold_and(conjuncts), Some(join)))
+}
+
+/// Where one column of an aggregated query's output comes from.
+#[derive(Debug)]
+enum OutputColumn {
+    /// The value of the `idx`-th `GROUP BY` column.
+    GroupBy(usize),
+    /// The finalized value of the `idx`-th aggregate.
+    Aggregate(usize),
+}
+
+#[derive(Debug)]
+enum QueryShape {
+    Project {
+        projections: Vec<((usize, usize), String)>,
+    },
+    Aggregate {
+        group_by: Vec<(usize, usize)>,
+        aggregates: Vec<(AggKind, ColumnType, (usize, usize))>,
+        output: Vec<OutputColumn>,
+        header: Vec<String>,
+    },
+}
+
+/// A query processed in the context of a schema, and ready to execute.
+#[derive(Debug)]
+struct CompiledQuery {
+    filters: Option<CompiledFilterTree>,
+    join: Option<JoinSpec>,
+    shape: QueryShape,
+}
+
+impl CompiledQuery {
+    /// Assemble a compiled, non-aggregating query from its already-resolved projections and
+    /// filters. When `num_tables` is two, the filters must include a top-level equi-join
+    /// predicate between the two tables (see [`extract_join`]).
+    fn new_project(
+        projections: Vec<((usize, usize), String)>,
+        filters: Option<CompiledFilterTree>,
+        num_tables: usize,
+    ) -> anyhow::Result<CompiledQuery> {
+        let (filters, join) = extract_join(filters, num_tables)?;
+        Ok(CompiledQuery {
+            filters,
+            join,
+            shape: QueryShape::Project { projections },
+        })
+    }
+
+    /// Assemble a compiled, aggregating (`GROUP BY`/aggregate projection) query.
+    fn new_aggregate(
+        group_by: Vec<(usize, usize)>,
+        aggregates: Vec<(AggKind, ColumnType, (usize, usize))>,
+        output: Vec<OutputColumn>,
+        header: Vec<String>,
+        filters: Option<CompiledFilterTree>,
+        num_tables: usize,
+    ) -> anyhow::Result<CompiledQuery> {
+        let (filters, join) = extract_join(filters, num_tables)?;
+        Ok(CompiledQuery {
+            filters,
+            join,
+            shape: QueryShape::Aggregate {
+                group_by,
+                aggregates,
+                output,
+                header,
+            },
+        })
     }
 
     fn out_header(&self) -> Vec<String> {
-        self.projections.iter().map(|c| c.1.clone()).collect()
+        match &self.shape {
+            QueryShape::Project { projections } => {
+                projections.iter().map(|(_, name)| name.clone()).collect()
+            }
+            QueryShape::Aggregate { header, .. } => header.clone(),
+        }
+    }
+}
+
+/// End-to-end execution tests (`parser.rs`'s own tests cover parsing and compilation only).
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loaded_csv_project_and_filter() -> anyhow::Result<()> {
+        let csv = "id,name\n1,alice\n2,bob\n";
+        let data = LoadedCSV::from_reader(csv.as_bytes())?;
+        let query = parse_query("PROJECT name FILTER id = \"2\"")?;
+        let rows: Vec<Vec<String>> = data.execute_query(query)?.collect();
+        assert_eq!(rows, vec![vec!["bob".to_string()]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rows_push_widens_types() {
+        let mut rows = Rows::empty(1);
+        rows.push(StringRecord::from(vec!["true"]));
+        assert_eq!(rows.types, vec![ColumnType::Boolean]);
+        rows.push(StringRecord::from(vec!["3"]));
+        assert_eq!(rows.types, vec![ColumnType::Integer]);
+        rows.push(StringRecord::from(vec!["3.5"]));
+        assert_eq!(rows.types, vec![ColumnType::Float]);
+        rows.push(StringRecord::from(vec!["abc"]));
+        assert_eq!(rows.types, vec![ColumnType::String]);
+    }
+
+    #[test]
+    fn test_parse_date_epoch_days() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11017));
+        assert_eq!(parse_date("not-a-date"), None);
+    }
+
+    #[test]
+    fn test_run_aggregation_group_by() -> anyhow::Result<()> {
+        let csv = "dept,salary\nsales,100\nsales,200\neng,300\n";
+        let data = LoadedCSV::from_reader(csv.as_bytes())?;
+        let query = parse_query(
+            "PROJECT dept, COUNT(dept), SUM(salary), AVG(salary), MIN(salary), MAX(salary) GROUP BY dept",
+        )?;
+        let mut rows: Vec<Vec<String>> = data.execute_query(query)?.collect();
+        rows.sort();
+        let row = |strs: &[&str]| strs.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            rows,
+            vec![
+                row(&["eng", "1", "300", "300", "300", "300"]),
+                row(&["sales", "2", "300", "150", "100", "200"]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_loaded_dataset_join_one_to_many_with_residual_filter() -> anyhow::Result<()> {
+        let a_csv = "id,name\n1,alice\n2,bob\n";
+        let b_csv = "user_id,total\n1,10\n1,20\n2,5\n";
+        let mut dataset = LoadedDataset::new();
+        dataset.insert("a", LoadedCSV::from_reader(a_csv.as_bytes())?);
+        dataset.insert("b", LoadedCSV::from_reader(b_csv.as_bytes())?);
+        let query = parse_query(
+            "PROJECT a.name, b.total FROM a, b FILTER a.id = b.user_id, b.total > \"5\"",
+        )?;
+        let mut rows: Vec<Vec<String>> = dataset.execute_query(query)?.collect();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["alice".to_string(), "10".to_string()],
+                vec!["alice".to_string(), "20".to_string()],
+            ]
+        );
+        Ok(())
     }
 }
@@ -1,56 +1,178 @@
-use crate::{ColumnType, CompiledExpr, CompiledFilter, CompiledQuery, Test};
+use crate::{
+    AggKind, ColumnType, CompiledExpr, CompiledFilter, CompiledFilterTree, CompiledQuery,
+    OutputColumn, Test,
+};
+use anyhow::Context as _;
+use nom::bytes::complete::is_not;
 use nom::bytes::complete::tag;
 use nom::character::complete::multispace0;
+use nom::character::complete::multispace1;
 use nom::character::complete::{self as parser, alphanumeric1};
-use nom::character::complete::{alpha1, multispace1};
-use nom::combinator::{eof, map, recognize};
-use nom::error::VerboseError;
-use nom::multi::separated_list1;
-use nom::sequence::{delimited, pair};
+use nom::combinator::{eof, map, opt, recognize};
+use nom::error::{context, convert_error, VerboseError};
+use nom::multi::{many0, many1, separated_list1};
+use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::{Finish, IResult};
 use std::collections::HashMap;
 
 /// A parsed query, but not yet resolved fully and ready to execute.
-/// Use [`execute_query`](crate::LoadedCSV::execute_query)
+/// Use [`execute_query`](crate::LoadedCSV::execute_query) for a single file, or
+/// [`LoadedDataset::execute_query`](crate::LoadedDataset::execute_query) when the
+/// query joins several tables named in its `FROM` clause.
 #[derive(Debug)]
 pub struct Query {
-    projections: Vec<String>,
-    filters: Vec<Filter>,
+    projections: Vec<Projection>,
+    tables: Vec<String>,
+    group_by: Vec<ColumnRef>,
+    filters: Option<FilterTree>,
+}
+
+impl Query {
+    /// The tables named in the query's `FROM` clause, in the order they were written.
+    /// Empty if the query has no `FROM` clause, in which case it must be run against a
+    /// single table (see [`LoadedCSV::execute_query`](crate::LoadedCSV::execute_query)).
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+}
+
+/// A reference to a column, optionally qualified with the table it belongs to, e.g. `total` or
+/// `a.total`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ColumnRef {
+    table: Option<String>,
+    column: String,
+}
+
+impl ColumnRef {
+    /// Resolve this reference against the schemas of the tables participating in the query, in
+    /// `FROM` order. An unqualified reference must be unambiguous across all of them.
+    fn resolve(
+        &self,
+        schemas: &[(&str, &[ColumnType], &[String])],
+    ) -> anyhow::Result<(usize, usize)> {
+        match &self.table {
+            Some(table) => {
+                let table_idx = schemas
+                    .iter()
+                    .position(|(name, ..)| name == table)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown table '{table}'"))?;
+                let column_idx = schemas[table_idx]
+                    .2
+                    .iter()
+                    .position(|c| c == &self.column)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown column '{table}.{}'", self.column))?;
+                Ok((table_idx, column_idx))
+            }
+            None => {
+                let mut found = None;
+                for (table_idx, (_, _, names)) in schemas.iter().enumerate() {
+                    if names.iter().any(|c| c == &self.column) {
+                        if found.is_some() {
+                            anyhow::bail!(
+                                "Column '{}' is ambiguous, qualify it with a table name",
+                                self.column
+                            );
+                        }
+                        found = Some(table_idx);
+                    }
+                }
+                let table_idx =
+                    found.ok_or_else(|| anyhow::anyhow!("Unknown column '{}'", self.column))?;
+                let column_idx = schemas[table_idx]
+                    .2
+                    .iter()
+                    .position(|c| c == &self.column)
+                    .expect("just located this column above");
+                Ok((table_idx, column_idx))
+            }
+        }
+    }
+
+    /// The name this column should be reported under in the output header.
+    fn display_name(&self) -> String {
+        match &self.table {
+            Some(table) => format!("{table}.{}", self.column),
+            None => self.column.clone(),
+        }
+    }
+}
+
+/// A single entry in a `PROJECT` clause: either a bare column, or an aggregate applied to one.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Projection {
+    Column(ColumnRef),
+    Aggregate(AggKind, ColumnRef),
 }
 
 #[derive(Debug, PartialEq, Eq)]
 /// An expression that is part of a filter.
 enum Expr {
-    Var { column_idx: String },
-    Const { val: String },
+    Var(ColumnRef),
+    Const {
+        val: String,
+    },
+    /// A `:name` placeholder, resolved against a caller-supplied bindings map at compile time
+    /// instead of carrying its value inline (see [`Query::compile`]).
+    Param {
+        name: String,
+    },
 }
 
 impl Expr {
-    /// If the expression is a variable and refers to a known column, return the column it refers to.
-    /// Otherwise return Ok(None).
-    fn resolve_column(&self, mapping: &HashMap<&str, usize>) -> anyhow::Result<Option<usize>> {
+    /// If the expression is a variable, return the table and column it refers to, or an error if
+    /// it names no known column. Otherwise return Ok(None).
+    fn resolve_column(
+        &self,
+        schemas: &[(&str, &[ColumnType], &[String])],
+    ) -> anyhow::Result<Option<(usize, usize)>> {
         match self {
-            Expr::Var { column_idx } => mapping
-                .get(column_idx.as_str())
-                .ok_or_else(|| anyhow::anyhow!("Unknown column '{column_idx}'"))
-                .copied()
-                .map(Some),
-            Expr::Const { .. } => Ok(None),
+            Expr::Var(col_ref) => col_ref.resolve(schemas).map(Some),
+            Expr::Const { .. } | Expr::Param { .. } => Ok(None),
         }
     }
 
-    fn resolve_const(self, column_type: ColumnType) -> anyhow::Result<CompiledExpr> {
-        match self {
+    fn resolve_const(
+        self,
+        column_type: ColumnType,
+        bindings: &HashMap<String, String>,
+    ) -> anyhow::Result<CompiledExpr> {
+        let val = match self {
             Expr::Var { .. } => {
                 anyhow::bail!("Expr is expected to be a variable. This is precondition violation.")
             }
-            Expr::Const { val } => match column_type {
-                ColumnType::String => Ok(CompiledExpr::StringConst { val }),
-                ColumnType::Integer => {
-                    let val = val.parse::<i64>()?;
-                    Ok(CompiledExpr::IntConst { val })
-                }
-            },
+            Expr::Const { val } => val,
+            Expr::Param { name } => bindings
+                .get(&name)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Missing binding for parameter ':{name}'"))?,
+        };
+        match column_type {
+            ColumnType::String => Ok(CompiledExpr::StringConst { val }),
+            ColumnType::Integer => {
+                let val = val
+                    .parse::<i64>()
+                    .with_context(|| format!("'{val}' is not a valid integer"))?;
+                Ok(CompiledExpr::IntConst { val })
+            }
+            ColumnType::Float => {
+                let val = val
+                    .parse::<f64>()
+                    .with_context(|| format!("'{val}' is not a valid float"))?;
+                Ok(CompiledExpr::FloatConst { val })
+            }
+            ColumnType::Boolean => {
+                let val = val.parse::<bool>().with_context(|| {
+                    format!("'{val}' is not a valid boolean, expected 'true' or 'false'")
+                })?;
+                Ok(CompiledExpr::BoolConst { val })
+            }
+            ColumnType::Date => {
+                let val = crate::parse_date(&val).ok_or_else(|| {
+                    anyhow::anyhow!("'{val}' is not a valid date, expected yyyy-mm-dd")
+                })?;
+                Ok(CompiledExpr::DateConst { val })
+            }
         }
     }
 }
@@ -64,39 +186,46 @@ struct Filter {
 }
 
 impl Filter {
-    /// Compile and validate the filter in the context of the provided schema which supplies
-    /// a mapping of column names to their index in the input data, and the types of those colums.
+    /// Compile and validate the filter in the context of the provided per-table schemas, each
+    /// supplying a table's column types and names. `schemas` is indexed in `FROM` order.
+    /// `bindings` resolves any `:param` placeholders appearing in the filter.
     fn compile(
         self,
-        column_types: &[ColumnType],
-        mapping: &HashMap<&str, usize>,
+        schemas: &[(&str, &[ColumnType], &[String])],
+        bindings: &HashMap<String, String>,
     ) -> anyhow::Result<CompiledFilter> {
-        let left_var = self.left.resolve_column(mapping)?;
-        let right_var = self.right.resolve_column(mapping)?;
+        let left_var = self.left.resolve_column(schemas)?;
+        let right_var = self.right.resolve_column(schemas)?;
 
         let ty = match (left_var, right_var) {
             (None, None) => {
                 anyhow::bail!("both operands are constants. Invalid test.",);
             }
-            (None, Some(t)) => column_types[t],
-            (Some(t), None) => column_types[t],
-            (Some(t1), Some(t2)) => {
-                let t1 = column_types[t1];
-                let t2 = column_types[t2];
-                if t1 == t2 {
-                    t1
+            (None, Some((t, c))) => schemas[t].1[c],
+            (Some((t, c)), None) => schemas[t].1[c],
+            (Some((t1, c1)), Some((t2, c2))) => {
+                let ty1 = schemas[t1].1[c1];
+                let ty2 = schemas[t2].1[c2];
+                if ty1 == ty2 {
+                    ty1
                 } else {
-                    anyhow::bail!("operand types inconsistent {t1:?} != {t2:?}",);
+                    anyhow::bail!("operand types inconsistent {ty1:?} != {ty2:?}",);
                 }
             }
         };
         let left = match left_var {
-            Some(column_idx) => CompiledExpr::Var { column_idx },
-            None => self.left.resolve_const(ty)?,
+            Some((table_idx, column_idx)) => CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            },
+            None => self.left.resolve_const(ty, bindings)?,
         };
         let right = match right_var {
-            Some(column_idx) => CompiledExpr::Var { column_idx },
-            None => self.right.resolve_const(ty)?,
+            Some((table_idx, column_idx)) => CompiledExpr::Var {
+                table_idx,
+                column_idx,
+            },
+            None => self.right.resolve_const(ty, bindings)?,
         };
         Ok(CompiledFilter {
             left,
@@ -107,105 +236,366 @@ impl Filter {
     }
 }
 
+/// A boolean combination of filters, built by [`parse_filter_tree`] with `NOT` binding tightest,
+/// then `AND`, then `OR`, and parentheses overriding all three.
+#[derive(Debug, PartialEq, Eq)]
+enum FilterTree {
+    Leaf(Filter),
+    And(Box<FilterTree>, Box<FilterTree>),
+    Or(Box<FilterTree>, Box<FilterTree>),
+    Not(Box<FilterTree>),
+}
+
+impl FilterTree {
+    /// Compile and validate every filter in the tree, in the context of the provided per-table
+    /// schemas. `bindings` resolves any `:param` placeholders appearing in the tree.
+    fn compile(
+        self,
+        schemas: &[(&str, &[ColumnType], &[String])],
+        bindings: &HashMap<String, String>,
+    ) -> anyhow::Result<CompiledFilterTree> {
+        Ok(match self {
+            FilterTree::Leaf(f) => CompiledFilterTree::Leaf(f.compile(schemas, bindings)?),
+            FilterTree::And(l, r) => CompiledFilterTree::And(
+                Box::new(l.compile(schemas, bindings)?),
+                Box::new(r.compile(schemas, bindings)?),
+            ),
+            FilterTree::Or(l, r) => CompiledFilterTree::Or(
+                Box::new(l.compile(schemas, bindings)?),
+                Box::new(r.compile(schemas, bindings)?),
+            ),
+            FilterTree::Not(t) => CompiledFilterTree::Not(Box::new(t.compile(schemas, bindings)?)),
+        })
+    }
+}
+
+fn parse_ident(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    // Identifiers may contain underscores (e.g. `user_id`), not just alphanumerics.
+    map(
+        recognize(many1(nom::branch::alt((alphanumeric1, tag("_"))))),
+        String::from,
+    )(i)
+}
+
+fn parse_column_ref(i: &str) -> IResult<&str, ColumnRef, VerboseError<&str>> {
+    let (i, first) = parse_ident(i)?;
+    let (i, qualifier) = opt(pair(parser::char('.'), parse_ident))(i)?;
+    Ok(match qualifier {
+        Some((_, column)) => (
+            i,
+            ColumnRef {
+                table: Some(first),
+                column,
+            },
+        ),
+        None => (
+            i,
+            ColumnRef {
+                table: None,
+                column: first,
+            },
+        ),
+    })
+}
+
+fn parse_agg_kind(i: &str) -> IResult<&str, AggKind, VerboseError<&str>> {
+    nom::branch::alt((
+        map(tag("COUNT"), |_| AggKind::Count),
+        map(tag("SUM"), |_| AggKind::Sum),
+        map(tag("AVG"), |_| AggKind::Avg),
+        map(tag("MIN"), |_| AggKind::Min),
+        map(tag("MAX"), |_| AggKind::Max),
+    ))(i)
+}
+
+fn parse_projection(i: &str) -> IResult<&str, Projection, VerboseError<&str>> {
+    nom::branch::alt((
+        map(
+            tuple((
+                parse_agg_kind,
+                parser::char('('),
+                parse_column_ref,
+                parser::char(')'),
+            )),
+            |(kind, _, col, _)| Projection::Aggregate(kind, col),
+        ),
+        map(parse_column_ref, Projection::Column),
+    ))(i)
+}
+
 fn parse_expr(i: &str) -> IResult<&str, Expr, VerboseError<&str>> {
     nom::branch::alt((
-        delimited(
-            parser::char('"'),
-            map(alphanumeric1, |v| Expr::Const {
-                val: String::from(v),
-            }),
-            parser::char('"'),
+        context(
+            "quoted constant",
+            delimited(
+                parser::char('"'),
+                map(is_not("\""), |v| Expr::Const {
+                    val: String::from(v),
+                }),
+                parser::char('"'),
+            ),
         ),
-        map(alpha1, |v| Expr::Var {
-            column_idx: String::from(v),
+        map(pair(parser::char(':'), parse_ident), |(_, name)| {
+            Expr::Param { name }
         }),
+        map(parse_column_ref, Expr::Var),
     ))(i)
 }
 
 fn parse_filter(orig: &str) -> IResult<&str, Filter, VerboseError<&str>> {
     let (i, left) = parse_expr(orig)?;
     let (i, _) = multispace0(i)?;
-    let (i, operator) = nom::branch::alt((tag("="), tag(">="), tag(">")))(i)?;
+    // Longer operators must be tried before their single-character prefixes, e.g. ">=" before ">".
+    let (i, operator) = context(
+        "comparison operator",
+        nom::branch::alt((
+            tag("!="),
+            tag(">="),
+            tag("<="),
+            tag("="),
+            tag(">"),
+            tag("<"),
+        )),
+    )(i)?;
     let (i, _) = multispace0(i)?;
     let (i, right) = parse_expr(i)?;
 
     let test = match operator {
         "=" => Test::Equal,
+        "!=" => Test::NotEqual,
         ">=" => Test::GreaterOrEqual,
         ">" => Test::Greater,
-        _ => unreachable!("Only three operators supported."),
+        "<=" => Test::LessOrEqual,
+        "<" => Test::Less,
+        _ => unreachable!("Only six operators supported."),
     };
     Ok((i, Filter { test, left, right }))
 }
 
+/// Entry point for a `FILTER` clause's boolean expression: `OR` binds loosest, then `AND`, then
+/// `NOT`, with parentheses overriding all three.
+fn parse_filter_tree(i: &str) -> IResult<&str, FilterTree, VerboseError<&str>> {
+    parse_or(i)
+}
+
+fn parse_or(i: &str) -> IResult<&str, FilterTree, VerboseError<&str>> {
+    let (i, first) = parse_and(i)?;
+    let (i, rest) = many0(preceded(
+        tuple((multispace1, tag("OR"), multispace1)),
+        parse_and,
+    ))(i)?;
+    Ok((
+        i,
+        rest.into_iter().fold(first, |acc, next| {
+            FilterTree::Or(Box::new(acc), Box::new(next))
+        }),
+    ))
+}
+
+fn parse_and(i: &str) -> IResult<&str, FilterTree, VerboseError<&str>> {
+    let (i, first) = parse_not(i)?;
+    let (i, rest) = many0(preceded(
+        tuple((multispace1, tag("AND"), multispace1)),
+        parse_not,
+    ))(i)?;
+    Ok((
+        i,
+        rest.into_iter().fold(first, |acc, next| {
+            FilterTree::And(Box::new(acc), Box::new(next))
+        }),
+    ))
+}
+
+fn parse_not(i: &str) -> IResult<&str, FilterTree, VerboseError<&str>> {
+    nom::branch::alt((
+        map(preceded(pair(tag("NOT"), multispace1), parse_not), |t| {
+            FilterTree::Not(Box::new(t))
+        }),
+        parse_filter_atom,
+    ))(i)
+}
+
+fn parse_filter_atom(i: &str) -> IResult<&str, FilterTree, VerboseError<&str>> {
+    nom::branch::alt((
+        delimited(
+            pair(parser::char('('), multispace0),
+            parse_or,
+            pair(multispace0, parser::char(')')),
+        ),
+        map(parse_filter, FilterTree::Leaf),
+    ))(i)
+}
+
 pub fn parse_query<'a>(ii: &'a str) -> anyhow::Result<Query> {
     let parser = |i: &'a str| -> IResult<(), Query, nom::error::VerboseError<_>> {
-        let (i, _) = tag("PROJECT")(i)?;
-        let (i, _) = multispace1(i)?;
-        let (i, projections) = separated_list1(
-            pair(parser::char(','), multispace0),
-            map(recognize(alphanumeric1), String::from),
+        let (i, _) = context("PROJECT keyword", tag("PROJECT"))(i)?;
+        let (i, (_, projections)) = context(
+            "column list",
+            pair(
+                multispace1,
+                separated_list1(pair(parser::char(','), multispace0), parse_projection),
+            ),
         )(i)?;
         let (i, _) = multispace0(i)?;
+
+        let (i, tables) = match opt(pair(tag("FROM"), multispace1))(i)? {
+            (i, Some(_)) => {
+                let (i, tables) =
+                    separated_list1(pair(parser::char(','), multispace0), parse_ident)(i)?;
+                let (i, _) = multispace0(i)?;
+                (i, tables)
+            }
+            (i, None) => (i, Vec::new()),
+        };
+
+        let (i, group_by) =
+            match opt(tuple((tag("GROUP"), multispace1, tag("BY"), multispace1)))(i)? {
+                (i, Some(_)) => {
+                    let (i, group_by) =
+                        separated_list1(pair(parser::char(','), multispace0), parse_column_ref)(i)?;
+                    let (i, _) = multispace0(i)?;
+                    (i, group_by)
+                }
+                (i, None) => (i, Vec::new()),
+            };
+
         if eof::<_, nom::error::Error<_>>(i).is_ok() {
             return Ok((
                 (),
                 Query {
                     projections,
-                    filters: Vec::new(),
+                    tables,
+                    group_by,
+                    filters: None,
                 },
             ));
         };
         let (i, _) = tag("FILTER")(i)?;
         let (i, _) = multispace1(i)?;
-        let (i, filters) = separated_list1(pair(parser::char(','), multispace0), parse_filter)(i)?;
+        // A comma is sugar for AND between top-level filter expressions.
+        let (i, filters) =
+            separated_list1(pair(parser::char(','), multispace0), parse_filter_tree)(i)?;
         let (rest, _) = multispace0(i)?;
         eof(rest)?;
+        let filters = filters
+            .into_iter()
+            .reduce(|acc, next| FilterTree::And(Box::new(acc), Box::new(next)));
         Ok((
             (),
             Query {
                 projections,
+                tables,
+                group_by,
                 filters,
             },
         ))
     };
     let result = parser(ii)
         .finish()
-        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        .map_err(|e| anyhow::anyhow!("Failed to parse query:\n{}", convert_error(ii, e)))?;
     Ok(result.1)
 }
 
 impl Query {
+    /// Compile this query against the schemas of the tables it runs over, given in `FROM` order
+    /// (or a single schema when the query has no `FROM` clause). `bindings` resolves any
+    /// `:param` placeholders appearing in the query's filters; a query with none can pass an
+    /// empty map.
     pub(crate) fn compile(
         self,
-        column_types: &[ColumnType],
-        column_names: &[String],
+        schemas: &[(&str, &[ColumnType], &[String])],
+        bindings: &HashMap<String, String>,
     ) -> anyhow::Result<CompiledQuery> {
-        // TODO: With better modelling we don't need this test.
+        anyhow::ensure!(!schemas.is_empty(), "A query needs at least one table.");
         anyhow::ensure!(
-            column_names.len() == column_types.len(),
-            "Column types and names don't match."
+            schemas.len() <= 2,
+            "Joins over more than two tables are not supported."
         );
-        let mut projections = Vec::with_capacity(self.projections.len());
-        let column_mapping = column_names
+        for (name, types, names) in schemas {
+            // TODO: With better modelling we don't need this test.
+            anyhow::ensure!(
+                names.len() == types.len(),
+                "Column types and names don't match for table '{name}'."
+            );
+        }
+
+        let filters = match self.filters {
+            Some(tree) => Some(tree.compile(schemas, bindings)?),
+            None => None,
+        };
+
+        let has_aggregates = self
+            .projections
             .iter()
-            .map(|s| s.as_str())
-            .zip(0..)
-            .collect::<HashMap<_, _>>();
-        for projection in &self.projections {
-            let Some(idx) = column_mapping.get(projection.as_str()) else {
-                anyhow::bail!("Unknown column name '{projection}'");
-            };
-            projections.push((*idx, String::from(projection)));
+            .any(|p| matches!(p, Projection::Aggregate(..)));
+        if has_aggregates || !self.group_by.is_empty() {
+            let group_by = self
+                .group_by
+                .iter()
+                .map(|c| c.resolve(schemas))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let mut aggregates = Vec::new();
+            let mut output = Vec::with_capacity(self.projections.len());
+            let mut header = Vec::with_capacity(self.projections.len());
+            for proj in &self.projections {
+                match proj {
+                    Projection::Column(col_ref) => {
+                        let location = col_ref.resolve(schemas)?;
+                        let group_idx =
+                            group_by
+                                .iter()
+                                .position(|g| *g == location)
+                                .ok_or_else(|| {
+                                    anyhow::anyhow!(
+                                "Column '{}' must appear in GROUP BY or be wrapped in an aggregate",
+                                col_ref.display_name()
+                            )
+                                })?;
+                        output.push(OutputColumn::GroupBy(group_idx));
+                        header.push(col_ref.display_name());
+                    }
+                    Projection::Aggregate(kind, col_ref) => {
+                        let location = col_ref.resolve(schemas)?;
+                        let ty = schemas[location.0].1[location.1];
+                        let allowed = match kind {
+                            AggKind::Count => true,
+                            AggKind::Sum | AggKind::Avg => ty == ColumnType::Integer,
+                            AggKind::Min | AggKind::Max => {
+                                matches!(ty, ColumnType::Integer | ColumnType::String)
+                            }
+                        };
+                        anyhow::ensure!(
+                            allowed,
+                            "{} is not supported on {ty:?} columns (column '{}')",
+                            kind.label(),
+                            col_ref.display_name()
+                        );
+                        output.push(OutputColumn::Aggregate(aggregates.len()));
+                        header.push(format!("{}({})", kind.label(), col_ref.display_name()));
+                        aggregates.push((*kind, ty, location));
+                    }
+                }
+            }
+            return CompiledQuery::new_aggregate(
+                group_by,
+                aggregates,
+                output,
+                header,
+                filters,
+                schemas.len(),
+            );
         }
-        let mut filters = Vec::with_capacity(self.filters.len());
-        for f in self.filters {
-            filters.push(f.compile(column_types, &column_mapping)?);
+
+        let mut projections = Vec::with_capacity(self.projections.len());
+        for proj in &self.projections {
+            let Projection::Column(col_ref) = proj else {
+                unreachable!("has_aggregates is false, so every projection is a bare column")
+            };
+            let location = col_ref.resolve(schemas)?;
+            projections.push((location, col_ref.display_name()));
         }
-        Ok(CompiledQuery {
-            projections,
-            filters,
-        })
+        CompiledQuery::new_project(projections, filters, schemas.len())
     }
 }
 
@@ -217,8 +607,16 @@ mod tests {
     #[test]
     fn test_parse1() -> anyhow::Result<()> {
         let query = parse_query("PROJECT a")?;
-        assert!(query.filters.is_empty());
-        assert_eq!(&query.projections, &["a"]);
+        assert!(query.filters.is_none());
+        assert!(query.tables.is_empty());
+        assert!(query.group_by.is_empty());
+        assert_eq!(
+            &query.projections,
+            &[Projection::Column(ColumnRef {
+                table: None,
+                column: "a".into()
+            })]
+        );
         Ok(())
     }
 
@@ -231,28 +629,104 @@ mod tests {
     fn test_parse3() -> anyhow::Result<()> {
         let query = parse_query("PROJECT a, b FILTER a > \"3\", b = \"4\", c >= \"5\"")?;
         let f1 = Filter {
-            left: Expr::Var {
-                column_idx: "a".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "a".into(),
+            }),
             right: Expr::Const { val: "3".into() },
             test: Test::Greater,
         };
         let f2 = Filter {
-            left: Expr::Var {
-                column_idx: "b".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "b".into(),
+            }),
             right: Expr::Const { val: "4".into() },
             test: Test::Equal,
         };
         let f3 = Filter {
-            left: Expr::Var {
-                column_idx: "c".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "c".into(),
+            }),
             right: Expr::Const { val: "5".into() },
             test: Test::GreaterOrEqual,
         };
-        assert_eq!(query.filters, [f1, f2, f3]);
-        assert_eq!(query.projections, ["a", "b"]);
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::And(
+                Box::new(FilterTree::And(
+                    Box::new(FilterTree::Leaf(f1)),
+                    Box::new(FilterTree::Leaf(f2))
+                )),
+                Box::new(FilterTree::Leaf(f3))
+            ))
+        );
+        assert!(query.tables.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_join() -> anyhow::Result<()> {
+        let query = parse_query("PROJECT a.name, b.total FROM a, b FILTER a.id = b.user_id")?;
+        assert_eq!(query.tables, ["a", "b"]);
+        assert_eq!(
+            query.projections,
+            [
+                Projection::Column(ColumnRef {
+                    table: Some("a".into()),
+                    column: "name".into()
+                }),
+                Projection::Column(ColumnRef {
+                    table: Some("b".into()),
+                    column: "total".into()
+                })
+            ]
+        );
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::Leaf(Filter {
+                left: Expr::Var(ColumnRef {
+                    table: Some("a".into()),
+                    column: "id".into()
+                }),
+                right: Expr::Var(ColumnRef {
+                    table: Some("b".into()),
+                    column: "user_id".into()
+                }),
+                test: Test::Equal,
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_group_by() -> anyhow::Result<()> {
+        let query =
+            parse_query("PROJECT dept, SUM(salary) GROUP BY dept FILTER salary > \"1000\"")?;
+        assert_eq!(
+            query.projections,
+            [
+                Projection::Column(ColumnRef {
+                    table: None,
+                    column: "dept".into()
+                }),
+                Projection::Aggregate(
+                    AggKind::Sum,
+                    ColumnRef {
+                        table: None,
+                        column: "salary".into()
+                    }
+                )
+            ]
+        );
+        assert_eq!(
+            query.group_by,
+            [ColumnRef {
+                table: None,
+                column: "dept".into()
+            }]
+        );
         Ok(())
     }
 
@@ -262,31 +736,296 @@ mod tests {
         let types: [ColumnType; 3] = [ColumnType::Integer, ColumnType::String, ColumnType::String];
 
         let f1 = Filter {
-            left: Expr::Var {
-                column_idx: "a".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "a".into(),
+            }),
             right: Expr::Const { val: "3".into() },
             test: Test::Greater,
         };
         let f2 = Filter {
-            left: Expr::Var {
-                column_idx: "b".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "b".into(),
+            }),
             right: Expr::Const { val: "4".into() },
             test: Test::Equal,
         };
         let f3 = Filter {
-            left: Expr::Var {
-                column_idx: "c".into(),
-            },
+            left: Expr::Var(ColumnRef {
+                table: None,
+                column: "c".into(),
+            }),
             right: Expr::Const { val: "5".into() },
             test: Test::GreaterOrEqual,
         };
         let query = Query {
-            projections: vec!["a".into(), "b".into()],
-            filters: vec![f1, f2, f3],
+            projections: vec![
+                Projection::Column(ColumnRef {
+                    table: None,
+                    column: "a".into(),
+                }),
+                Projection::Column(ColumnRef {
+                    table: None,
+                    column: "b".into(),
+                }),
+            ],
+            tables: Vec::new(),
+            group_by: Vec::new(),
+            filters: Some(FilterTree::And(
+                Box::new(FilterTree::And(
+                    Box::new(FilterTree::Leaf(f1)),
+                    Box::new(FilterTree::Leaf(f2)),
+                )),
+                Box::new(FilterTree::Leaf(f3)),
+            )),
+        };
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_join() -> anyhow::Result<()> {
+        let a_names: [String; 2] = ["id".into(), "name".into()];
+        let a_types: [ColumnType; 2] = [ColumnType::Integer, ColumnType::String];
+        let b_names: [String; 2] = ["user_id".into(), "total".into()];
+        let b_types: [ColumnType; 2] = [ColumnType::Integer, ColumnType::Integer];
+
+        let query = parse_query("PROJECT a.name, b.total FROM a, b FILTER a.id = b.user_id")?;
+        query.compile(
+            &[("a", &a_types, &a_names), ("b", &b_types, &b_names)],
+            &HashMap::new(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_group_by() -> anyhow::Result<()> {
+        let names: [String; 2] = ["dept".into(), "salary".into()];
+        let types: [ColumnType; 2] = [ColumnType::String, ColumnType::Integer];
+
+        let query =
+            parse_query("PROJECT dept, SUM(salary) GROUP BY dept FILTER salary > \"1000\"")?;
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_sum_rejects_string() {
+        let names: [String; 2] = ["dept".into(), "salary".into()];
+        let types: [ColumnType; 2] = [ColumnType::String, ColumnType::String];
+
+        let query = parse_query("PROJECT dept, SUM(salary) GROUP BY dept").unwrap();
+        assert!(query
+            .compile(&[("", &types, &names)], &HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_param() -> anyhow::Result<()> {
+        let query = parse_query("PROJECT a FILTER a = :min_a")?;
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::Leaf(Filter {
+                left: Expr::Var(ColumnRef {
+                    table: None,
+                    column: "a".into()
+                }),
+                right: Expr::Param {
+                    name: "min_a".into()
+                },
+                test: Test::Equal,
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_param() -> anyhow::Result<()> {
+        let names: [String; 1] = ["a".into()];
+        let types: [ColumnType; 1] = [ColumnType::Integer];
+
+        let query = parse_query("PROJECT a FILTER a = :min_a")?;
+        let bindings = HashMap::from([("min_a".to_string(), "3".to_string())]);
+        query.compile(&[("", &types, &names)], &bindings)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_param_missing_binding() {
+        let names: [String; 1] = ["a".into()];
+        let types: [ColumnType; 1] = [ColumnType::Integer];
+
+        let query = parse_query("PROJECT a FILTER a = :min_a").unwrap();
+        assert!(query
+            .compile(&[("", &types, &names)], &HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_comparison_operators() -> anyhow::Result<()> {
+        let query = parse_query("PROJECT a FILTER a != \"1\", a <= \"2\", a < \"3\"")?;
+        let make = |test, val: &str| {
+            FilterTree::Leaf(Filter {
+                left: Expr::Var(ColumnRef {
+                    table: None,
+                    column: "a".into(),
+                }),
+                right: Expr::Const { val: val.into() },
+                test,
+            })
+        };
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::And(
+                Box::new(FilterTree::And(
+                    Box::new(make(Test::NotEqual, "1")),
+                    Box::new(make(Test::LessOrEqual, "2")),
+                )),
+                Box::new(make(Test::Less, "3")),
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_boolean_precedence() -> anyhow::Result<()> {
+        // NOT binds tighter than AND, which binds tighter than OR.
+        let query = parse_query("PROJECT a FILTER a > \"1\" OR NOT a > \"2\" AND a > \"3\"")?;
+        let gt = |val: &str| {
+            FilterTree::Leaf(Filter {
+                left: Expr::Var(ColumnRef {
+                    table: None,
+                    column: "a".into(),
+                }),
+                right: Expr::Const { val: val.into() },
+                test: Test::Greater,
+            })
+        };
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::Or(
+                Box::new(gt("1")),
+                Box::new(FilterTree::And(
+                    Box::new(FilterTree::Not(Box::new(gt("2")))),
+                    Box::new(gt("3")),
+                )),
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_boolean_parens() -> anyhow::Result<()> {
+        let query = parse_query("PROJECT a FILTER (a > \"1\" AND a < \"2\") OR a = \"3\"")?;
+        let cmp = |test, val: &str| {
+            FilterTree::Leaf(Filter {
+                left: Expr::Var(ColumnRef {
+                    table: None,
+                    column: "a".into(),
+                }),
+                right: Expr::Const { val: val.into() },
+                test,
+            })
         };
-        query.compile(&types, &names)?;
+        assert_eq!(
+            query.filters,
+            Some(FilterTree::Or(
+                Box::new(FilterTree::And(
+                    Box::new(cmp(Test::Greater, "1")),
+                    Box::new(cmp(Test::Less, "2")),
+                )),
+                Box::new(cmp(Test::Equal, "3")),
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_boolean_tree() -> anyhow::Result<()> {
+        let names: [String; 1] = ["a".into()];
+        let types: [ColumnType; 1] = [ColumnType::Integer];
+
+        let query = parse_query("PROJECT a FILTER (a > \"3\" AND a < \"10\") OR NOT a = \"0\"")?;
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_reports_expected_operator() {
+        let err = parse_query("PROJECT a FILTER a ~ \"3\"").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("comparison operator"));
+    }
+
+    #[test]
+    fn test_parse_error_reports_bad_projection() {
+        let err = parse_query("PROJECT").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("PROJECT keyword") || message.contains("column list"));
+    }
+
+    #[test]
+    fn test_compile_float_filter() -> anyhow::Result<()> {
+        let names: [String; 1] = ["price".into()];
+        let types: [ColumnType; 1] = [ColumnType::Float];
+
+        let query = parse_query("PROJECT price FILTER price > \"3.5\"")?;
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_bool_filter() -> anyhow::Result<()> {
+        let names: [String; 1] = ["active".into()];
+        let types: [ColumnType; 1] = [ColumnType::Boolean];
+
+        let query = parse_query("PROJECT active FILTER active = \"true\"")?;
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_date_filter() -> anyhow::Result<()> {
+        let names: [String; 1] = ["signup".into()];
+        let types: [ColumnType; 1] = [ColumnType::Date];
+
+        let query = parse_query("PROJECT signup FILTER signup >= \"2024-01-01\"")?;
+        query.compile(&[("", &types, &names)], &HashMap::new())?;
         Ok(())
     }
+
+    #[test]
+    fn test_compile_date_filter_rejects_bad_literal() {
+        let names: [String; 1] = ["signup".into()];
+        let types: [ColumnType; 1] = [ColumnType::Date];
+
+        let query = parse_query("PROJECT signup FILTER signup >= \"not-a-date\"").unwrap();
+        assert!(query
+            .compile(&[("", &types, &names)], &HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_compile_date_filter_rejects_nonexistent_day() {
+        let names: [String; 1] = ["signup".into()];
+        let types: [ColumnType; 1] = [ColumnType::Date];
+
+        // 2023 is not a leap year, so February only has 28 days.
+        let query = parse_query("PROJECT signup FILTER signup >= \"2023-02-29\"").unwrap();
+        assert!(query
+            .compile(&[("", &types, &names)], &HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_compile_min_rejects_float() {
+        let names: [String; 1] = ["price".into()];
+        let types: [ColumnType; 1] = [ColumnType::Float];
+
+        let query = parse_query("PROJECT MIN(price)").unwrap();
+        assert!(query
+            .compile(&[("", &types, &names)], &HashMap::new())
+            .is_err());
+    }
 }